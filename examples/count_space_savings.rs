@@ -7,10 +7,19 @@ use std::collections::HashMap;
 use std::collections::HashSet;
 use std::path::PathBuf;
 use std::sync::Arc;
+use thrumzip::chunk_dedup::analyze_chunk_dedup;
+use thrumzip::content_dedup::scan_by_content;
+use thrumzip::entry_hashes::compute_entry_hashes;
 use thrumzip::get_zips::get_zips;
 use thrumzip::path_inside_zip::PathInsideZip;
 use thrumzip::path_to_zip::PathToZip;
+use thrumzip::repack::repack;
+use thrumzip::report::DedupReport;
 use thrumzip::state::profiles::Profile;
+use thrumzip::state::profiles::ReportFormat;
+use thrumzip::store::Store;
+use thrumzip::verify::EntryLocation;
+use thrumzip::verify::verify_path_collisions;
 use tracing::Level;
 
 fn format_bytes(bytes: u64) -> String {
@@ -43,6 +52,10 @@ async fn main() -> eyre::Result<()> {
     let mut map: HashMap<PathToZip, HashSet<PathInsideZip>> = HashMap::new();
     // new: map from (zip_path, entry_path) to compressed_size
     let mut size_map: HashMap<(PathToZip, PathInsideZip), u64> = HashMap::new();
+    // map from (zip_path, entry_path) to the entry's CRC-32, from the central directory
+    let mut crc_map: HashMap<(PathToZip, PathInsideZip), u32> = HashMap::new();
+    // map from (zip_path, entry_path) to the entry's decompressed size
+    let mut uncompressed_size_map: HashMap<(PathToZip, PathInsideZip), u64> = HashMap::new();
     for zip_path in &zip_paths {
         println!("Processing {zip_path:?}");
         let f = Arc::new(RandomAccessFile::open(zip_path)?);
@@ -61,13 +74,16 @@ async fn main() -> eyre::Result<()> {
                 names.insert(name.clone()),
                 "Duplicate entry {name:?} in archive {zip_path:?}"
             );
-            // record compressed size
-            size_map.insert((zip_path.clone(), name), entry.compressed_size);
+            // record compressed size and CRC-32 for the cheap tier of collision verification
+            size_map.insert((zip_path.clone(), name.clone()), entry.compressed_size);
+            crc_map.insert((zip_path.clone(), name.clone()), entry.crc32);
+            uncompressed_size_map.insert((zip_path.clone(), name), entry.uncompressed_size);
         }
         map.insert(zip_path.clone(), names);
     }
 
     // for each pair of zip files, print how many common paths they share
+    let mut pair_records = Vec::new();
     for i in 0..zip_paths.len() {
         for j in (i + 1)..zip_paths.len() {
             let p1 = &zip_paths[i];
@@ -97,6 +113,12 @@ async fn main() -> eyre::Result<()> {
                     pair_bytes,
                     format_bytes(pair_bytes)
                 );
+                pair_records.push(DedupReport::pair_overlap(
+                    p1,
+                    p2,
+                    common as u64,
+                    pair_bytes,
+                ));
             }
         }
     }
@@ -122,10 +144,10 @@ async fn main() -> eyre::Result<()> {
                 .copied()
                 .unwrap_or(0);
             total += size;
-            if let Some(zips) = entry_map.get(entry) {
-                if zips.len() > 1 {
-                    dup += size;
-                }
+            if let Some(zips) = entry_map.get(entry)
+                && zips.len() > 1
+            {
+                dup += size;
             }
         }
         file_bytes.insert(zip_path, total);
@@ -133,9 +155,11 @@ async fn main() -> eyre::Result<()> {
     }
 
     // Print per-file duplicate stats
+    let mut zip_records = Vec::new();
     for zip_path in &zip_paths {
         let total = file_bytes[zip_path];
         let dup = file_dup_bytes[zip_path];
+        zip_records.push(DedupReport::zip_stats(zip_path, total, dup));
         let percent = if total > 0 {
             (dup as f64) / (total as f64) * 100.0
         } else {
@@ -149,22 +173,54 @@ async fn main() -> eyre::Result<()> {
         );
     }
 
-    // Calculate total savable space (all-but-one for each entry)
+    // Build the same path groupings as `EntryLocation`s so they can be run through
+    // the content-verification pass: a shared `PathInsideZip` is only a candidate
+    // for dedup until its bytes are actually confirmed to match.
+    let mut location_map: HashMap<PathInsideZip, Vec<EntryLocation>> = HashMap::new();
+    for ((zip_path, entry_path), &compressed_size) in &size_map {
+        let crc32 = crc_map
+            .get(&(zip_path.clone(), entry_path.clone()))
+            .copied()
+            .unwrap_or(0);
+        let uncompressed_size = uncompressed_size_map
+            .get(&(zip_path.clone(), entry_path.clone()))
+            .copied()
+            .unwrap_or(0);
+        location_map
+            .entry(entry_path.clone())
+            .or_default()
+            .push(EntryLocation {
+                zip_path: zip_path.clone(),
+                inside_zip: entry_path.clone(),
+                compressed_size,
+                uncompressed_size,
+                crc32,
+            });
+    }
+    // Precompute every hash the analyses below need from a single read pass
+    // per entry, instead of each analysis reopening and re-streaming the
+    // same archives independently.
+    let all_locations: Vec<EntryLocation> = location_map.values().flatten().cloned().collect();
+    let hashes = compute_entry_hashes(&all_locations).await?;
+
+    let verified = verify_path_collisions(&location_map, &hashes)?;
+
+    // Calculate total savable space (all-but-one for each *verified* content class)
     let mut total_savable = 0u64;
     let mut total_bytes = 0u64;
-    for zips in entry_map.values() {
-        if zips.len() > 1 {
-            // Sort by size, keep one, sum the rest
-            let mut sizes: Vec<u64> = zips.iter().map(|(_, s)| *s).collect();
-            sizes.sort_unstable();
-            // Save all but one
-            for s in &sizes[..sizes.len() - 1] {
-                total_savable += *s;
+    for classes in verified.values() {
+        for class in classes {
+            if class.members.len() > 1 {
+                // Sort by size, keep one, sum the rest
+                let mut sizes: Vec<u64> = class.members.iter().map(|m| m.compressed_size).collect();
+                sizes.sort_unstable();
+                for s in &sizes[..sizes.len() - 1] {
+                    total_savable += *s;
+                }
+            }
+            for member in &class.members {
+                total_bytes += member.compressed_size;
             }
-        }
-        // Count all bytes for total
-        for (_, s) in zips {
-            total_bytes += *s;
         }
     }
     let percent_reduction = if total_bytes > 0 {
@@ -173,11 +229,96 @@ async fn main() -> eyre::Result<()> {
         0.0
     };
     println!(
-        "Total deduplicatable bytes: {} ({:.2}% reduction)",
+        "Total deduplicatable bytes: {} ({:.2}% reduction, content-verified)",
         format_bytes(total_savable),
         percent_reduction
     );
 
+    // Machine-readable view of the same numbers, for scripting against.
+    let dedup_report = DedupReport {
+        zips: zip_records,
+        pairs: pair_records,
+        total_savable_bytes: total_savable,
+        total_bytes,
+        percent_reduction,
+    };
+    match profile.report_format {
+        ReportFormat::Human => {}
+        ReportFormat::Json => println!("{}", dedup_report.to_json()?),
+        ReportFormat::Ndjson => print!("{}", dedup_report.to_ndjson()?),
+    }
+
+    // Chunk-level dedup: catches shifted/appended content that whole-entry
+    // hashing reports as entirely unique.
+    let chunk_report = analyze_chunk_dedup(&all_locations, &hashes)?;
+    let chunk_percent_reduction = if chunk_report.total_bytes > 0 {
+        (chunk_report.deduplicatable_bytes as f64) / (chunk_report.total_bytes as f64) * 100.0
+    } else {
+        0.0
+    };
+    println!(
+        "Sub-file chunk dedup: {} of {} chunks savable ({} unique), {} ({:.2}% reduction)",
+        chunk_report.total_chunks - chunk_report.unique_chunks,
+        chunk_report.total_chunks,
+        chunk_report.unique_chunks,
+        format_bytes(chunk_report.deduplicatable_bytes),
+        chunk_percent_reduction
+    );
+
+    // Name-agnostic view: the same content living at different paths (e.g.
+    // the same photo under two different album names) is invisible to the
+    // path-keyed stats above, so report it separately.
+    let content_report = scan_by_content(&all_locations, &hashes)?;
+    println!(
+        "Content-based dedup (name-agnostic): {} duplicate groups, {} reclaimable",
+        content_report.groups.len(),
+        format_bytes(content_report.reclaimable_bytes)
+    );
+    for group in &content_report.groups {
+        let locations: Vec<String> = group
+            .locations
+            .iter()
+            .map(|loc| format!("{}:{}", loc.zip_path, loc.inside_zip))
+            .collect();
+        println!(
+            "  {} bytes each, {} copies: {}",
+            group.uncompressed_size,
+            group.locations.len(),
+            locations.join(", ")
+        );
+    }
+
+    // If the profile asks for it, materialize a deduplicated store instead of
+    // only reporting the savings above.
+    if let Some(store_root) = &profile.store_root {
+        let store = Store::new(store_root.clone());
+        let stats = store.build(&zip_paths, &hashes).await?;
+        println!(
+            "Store at {:?}: wrote {} unique blobs ({}) across {} entries, {} already deduplicated",
+            store_root,
+            stats.blobs_written,
+            format_bytes(stats.bytes_written),
+            stats.entries_written,
+            format_bytes(stats.bytes_deduplicated)
+        );
+    }
+
+    // If the profile asks for it, repack archives with low usage ratios into
+    // pruned copies instead of only reporting the savings above.
+    if let Some(repack_config) = &profile.repack {
+        let usages = repack(&zip_paths, &verified, repack_config).await?;
+        for usage in &usages {
+            println!(
+                "{}: usage ratio {:.2}% ({} retained of {}){}",
+                usage.zip_path.display(),
+                usage.usage_ratio() * 100.0,
+                format_bytes(usage.retained_bytes),
+                format_bytes(usage.original_bytes),
+                if usage.rewritten { ", rewritten" } else { "" }
+            );
+        }
+    }
+
     println!("All entries processed successfully");
     Ok(())
 }
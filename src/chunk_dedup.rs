@@ -0,0 +1,70 @@
+//! Sub-file deduplication across FastCDC chunks.
+//!
+//! Whole-entry hashing treats a file that merely grew by a few appended
+//! records as entirely unique. Chunking each entry and hashing the chunks
+//! individually surfaces the overlap that whole-entry comparison misses.
+
+use std::collections::HashMap;
+
+use crate::entry_hashes::EntryHashes;
+use crate::path_inside_zip::PathInsideZip;
+use crate::path_to_zip::PathToZip;
+use crate::verify::EntryLocation;
+
+/// A chunk's content identity, independent of which entry it came from.
+pub type ChunkHash = u128;
+
+/// Chunk-level dedup statistics across a set of entries.
+#[derive(Debug, Default, Clone)]
+pub struct ChunkDedupReport {
+    pub total_bytes: u64,
+    pub deduplicatable_bytes: u64,
+    pub unique_chunks: usize,
+    pub total_chunks: usize,
+}
+
+/// Tally chunk-level dedup stats for `entries` from their precomputed chunk
+/// hashes, and report how many bytes are reclaimable if only one copy of
+/// each unique chunk were kept.
+///
+/// `hashes` must already hold an [`EntryHashes`] entry for every location in
+/// `entries` (see [`crate::entry_hashes::compute_entry_hashes`]).
+pub fn analyze_chunk_dedup(
+    entries: &[EntryLocation],
+    hashes: &HashMap<(PathToZip, PathInsideZip), EntryHashes>,
+) -> eyre::Result<ChunkDedupReport> {
+    // hash -> (occurrence count, chunk length in bytes)
+    let mut table: HashMap<ChunkHash, (u64, u64)> = HashMap::new();
+
+    for loc in entries {
+        let entry_hashes = hashes
+            .get(&(loc.zip_path.clone(), loc.inside_zip.clone()))
+            .ok_or_else(|| {
+                eyre::eyre!(
+                    "No precomputed hashes for {:?} in {:?}",
+                    loc.inside_zip,
+                    loc.zip_path
+                )
+            })?;
+
+        for &(hash, len) in &entry_hashes.chunks {
+            let slot = table.entry(hash).or_insert((0, len));
+            slot.0 += 1;
+        }
+    }
+
+    let total_chunks: usize = table.values().map(|(count, _)| *count as usize).sum();
+    let total_bytes: u64 = table.values().map(|(count, len)| count * len).sum();
+    let deduplicatable_bytes: u64 = table
+        .values()
+        .filter(|(count, _)| *count > 1)
+        .map(|(count, len)| len * (count - 1))
+        .sum();
+
+    Ok(ChunkDedupReport {
+        total_bytes,
+        deduplicatable_bytes,
+        unique_chunks: table.len(),
+        total_chunks,
+    })
+}
@@ -0,0 +1,214 @@
+//! FastCDC content-defined chunking.
+//!
+//! Splitting a stream into content-defined chunks lets sub-file dedup survive
+//! a file that has shifted or grown: a few bytes appended to a 2 GB
+//! `messages.json` only invalidates the chunk(s) touching the edit, not the
+//! whole file, the way whole-entry hashing would.
+
+use tokio::io::AsyncRead;
+use tokio::io::AsyncReadExt;
+
+/// Bytes skipped at the start of every chunk before cut-point scanning begins.
+pub const MIN_SIZE: usize = 2 * 1024;
+/// The chunk size the mask pair is tuned to average out to.
+pub const AVG_SIZE: usize = 8 * 1024;
+/// Hard upper bound on chunk size; a cut is forced here even with no mask hit.
+pub const MAX_SIZE: usize = 64 * 1024;
+
+const fn splitmix64(seed: u64) -> u64 {
+    let mut z = seed.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+const fn gear_table() -> [u64; 256] {
+    let mut table = [0u64; 256];
+    let mut state = 0x2545_F491_4F6C_DD1D_u64;
+    let mut i = 0;
+    while i < 256 {
+        state = splitmix64(state);
+        table[i] = state;
+        i += 1;
+    }
+    table
+}
+
+/// Precomputed random 64-bit fingerprint contributed by each possible byte value.
+static GEAR: [u64; 256] = gear_table();
+
+/// Cut-point mask used while the in-progress chunk is still below [`AVG_SIZE`].
+/// More set bits than [`MASK_LARGE`], so it's harder to satisfy and cuts are rarer.
+const MASK_SMALL: u64 = 0x0003_5900_3530_0000;
+/// Cut-point mask used once the in-progress chunk has passed [`AVG_SIZE`].
+/// Fewer set bits than [`MASK_SMALL`], so it's easier to satisfy and cuts come sooner.
+const MASK_LARGE: u64 = 0x0000_d900_0350_0000;
+
+/// A single content-defined chunk, in order, starting at `offset` within its stream.
+#[derive(Debug, Clone)]
+pub struct Chunk {
+    pub offset: u64,
+    pub data: Vec<u8>,
+}
+
+/// Find the cut point within `window`, a buffer of at most [`MAX_SIZE`] bytes
+/// that starts a new chunk at index 0.
+fn cut_point(window: &[u8]) -> usize {
+    let len = window.len();
+    if len <= MIN_SIZE {
+        return len;
+    }
+
+    let mut fp: u64 = 0;
+    let mut i = MIN_SIZE;
+    while i < len {
+        fp = (fp << 1).wrapping_add(GEAR[window[i] as usize]);
+        let mask = if i < AVG_SIZE { MASK_SMALL } else { MASK_LARGE };
+        if fp & mask == 0 {
+            return i + 1;
+        }
+        i += 1;
+    }
+    len
+}
+
+/// Split a decompressed stream into FastCDC chunks.
+///
+/// Reads incrementally in windows of at most [`MAX_SIZE`] bytes. The returned
+/// chunks own their data, so the result holds every byte of the stream in
+/// memory at once.
+pub async fn chunk_stream(mut reader: impl AsyncRead + Unpin) -> eyre::Result<Vec<Chunk>> {
+    let mut chunks = Vec::new();
+    let mut offset = 0u64;
+    let mut leftover: Vec<u8> = Vec::new();
+    let mut buf = vec![0u8; MAX_SIZE];
+
+    loop {
+        let carried = leftover.len();
+        buf[..carried].copy_from_slice(&leftover);
+        leftover.clear();
+
+        let mut filled = carried;
+        while filled < buf.len() {
+            let n = reader.read(&mut buf[filled..]).await?;
+            if n == 0 {
+                break;
+            }
+            filled += n;
+        }
+        if filled == 0 {
+            break;
+        }
+
+        let window = &buf[..filled];
+        let at_stream_end = filled < MAX_SIZE;
+        let cut = if at_stream_end {
+            filled
+        } else {
+            cut_point(window)
+        };
+
+        chunks.push(Chunk {
+            offset,
+            data: window[..cut].to_vec(),
+        });
+        offset += cut as u64;
+        leftover.extend_from_slice(&window[cut..]);
+    }
+
+    Ok(chunks)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reassemble(chunks: &[Chunk]) -> Vec<u8> {
+        chunks.iter().flat_map(|c| c.data.clone()).collect()
+    }
+
+    #[tokio::test]
+    async fn empty_stream_yields_no_chunks() {
+        let chunks = chunk_stream(&b""[..]).await.unwrap();
+        assert!(chunks.is_empty());
+    }
+
+    #[tokio::test]
+    async fn chunks_reassemble_to_the_original_bytes() {
+        // A mix of repeating and pseudo-random-looking bytes so the cut
+        // point logic actually exercises more than one chunk boundary.
+        let mut data = Vec::new();
+        for i in 0..(5 * MAX_SIZE) {
+            data.push((i * 2654435761).wrapping_add(i) as u8);
+        }
+
+        let chunks = chunk_stream(&data[..]).await.unwrap();
+        assert_eq!(reassemble(&chunks), data);
+    }
+
+    #[tokio::test]
+    async fn no_chunk_exceeds_max_size() {
+        let data = vec![0u8; 5 * MAX_SIZE];
+        let chunks = chunk_stream(&data[..]).await.unwrap();
+        assert!(chunks.iter().all(|c| c.data.len() <= MAX_SIZE));
+    }
+
+    #[tokio::test]
+    async fn chunk_offsets_are_contiguous() {
+        let mut data = Vec::new();
+        for i in 0..(3 * MAX_SIZE) {
+            data.push((i * 2654435761).wrapping_add(i) as u8);
+        }
+
+        let chunks = chunk_stream(&data[..]).await.unwrap();
+        let mut expected_offset = 0u64;
+        for chunk in &chunks {
+            assert_eq!(chunk.offset, expected_offset);
+            expected_offset += chunk.data.len() as u64;
+        }
+    }
+
+    #[tokio::test]
+    async fn a_single_short_stream_is_one_chunk() {
+        let data = vec![7u8; MIN_SIZE / 2];
+        let chunks = chunk_stream(&data[..]).await.unwrap();
+        assert_eq!(chunks.len(), 1);
+        assert_eq!(chunks[0].data, data);
+    }
+
+    #[tokio::test]
+    async fn appending_bytes_only_changes_the_trailing_chunk() {
+        // The whole point of content-defined chunking: inserting bytes at
+        // the end of a stream should leave every earlier chunk boundary
+        // untouched, unlike fixed-size chunking.
+        let mut data = Vec::new();
+        for i in 0..(4 * MAX_SIZE) {
+            data.push((i * 2654435761).wrapping_add(i) as u8);
+        }
+        let mut appended = data.clone();
+        appended.extend_from_slice(b"some extra appended bytes at the end");
+
+        let original_chunks = chunk_stream(&data[..]).await.unwrap();
+        let appended_chunks = chunk_stream(&appended[..]).await.unwrap();
+
+        let shared = original_chunks.len() - 1;
+        for i in 0..shared {
+            assert_eq!(
+                original_chunks[i].data, appended_chunks[i].data,
+                "chunk {i} changed after appending to the end of the stream"
+            );
+        }
+    }
+
+    #[test]
+    fn cut_point_never_exceeds_window_length() {
+        let window = vec![0u8; MAX_SIZE];
+        assert!(cut_point(&window) <= window.len());
+    }
+
+    #[test]
+    fn cut_point_on_a_window_at_or_below_min_size_takes_it_all() {
+        let window = vec![0u8; MIN_SIZE];
+        assert_eq!(cut_point(&window), MIN_SIZE);
+    }
+}
@@ -0,0 +1,191 @@
+//! Name-agnostic duplicate detection.
+//!
+//! `entry_map` and the path-collision verification in [`crate::verify`] are
+//! keyed by `PathInsideZip`, so two copies of the same photo living under
+//! different album paths never show up as duplicates. This module groups by
+//! `(uncompressed_size, full content hash)` instead, the czkawka/ddh-style
+//! view that finds duplication hidden by renames or reorganized albums.
+
+use std::collections::HashMap;
+
+use crate::entry_hashes::EntryHashes;
+use crate::path_inside_zip::PathInsideZip;
+use crate::path_to_zip::PathToZip;
+use crate::verify::EntryLocation;
+
+/// All locations found to hold one particular piece of content, regardless
+/// of what each copy happened to be named.
+#[derive(Debug, Clone)]
+pub struct ContentDuplicateGroup {
+    pub full_hash: u128,
+    pub uncompressed_size: u64,
+    pub locations: Vec<EntryLocation>,
+}
+
+/// The name-agnostic view of dedup: every group of content duplicates found,
+/// and the bytes reclaimable by keeping a single copy of each.
+#[derive(Debug, Default, Clone)]
+pub struct ContentDedupReport {
+    pub groups: Vec<ContentDuplicateGroup>,
+    pub reclaimable_bytes: u64,
+}
+
+/// Scan `entries` for duplicate content irrespective of `PathInsideZip`.
+///
+/// Groups by `(uncompressed_size, full content hash)` so two copies of an
+/// identically-sized but different file don't collide before their hashes
+/// are compared. `hashes` must already hold an [`EntryHashes`] entry for
+/// every location in `entries` (see
+/// [`crate::entry_hashes::compute_entry_hashes`]).
+pub fn scan_by_content(
+    entries: &[EntryLocation],
+    hashes: &HashMap<(PathToZip, PathInsideZip), EntryHashes>,
+) -> eyre::Result<ContentDedupReport> {
+    // Tier 1: group by the cheap, already-known uncompressed size.
+    let mut by_size: HashMap<u64, Vec<&EntryLocation>> = HashMap::new();
+    for loc in entries {
+        by_size.entry(loc.uncompressed_size).or_default().push(loc);
+    }
+
+    let mut groups: Vec<ContentDuplicateGroup> = Vec::new();
+    let mut reclaimable_bytes = 0u64;
+
+    for (uncompressed_size, candidates) in by_size {
+        if candidates.len() < 2 {
+            continue;
+        }
+
+        let mut by_hash: HashMap<u128, Vec<EntryLocation>> = HashMap::new();
+        for loc in candidates {
+            let entry_hashes = hashes
+                .get(&(loc.zip_path.clone(), loc.inside_zip.clone()))
+                .ok_or_else(|| {
+                    eyre::eyre!(
+                        "No precomputed hashes for {:?} in {:?}",
+                        loc.inside_zip,
+                        loc.zip_path
+                    )
+                })?;
+            by_hash
+                .entry(entry_hashes.full_hash)
+                .or_default()
+                .push(loc.clone());
+        }
+
+        for (full_hash, locations) in by_hash {
+            if locations.len() < 2 {
+                continue;
+            }
+            reclaimable_bytes += uncompressed_size * (locations.len() as u64 - 1);
+            groups.push(ContentDuplicateGroup {
+                full_hash,
+                uncompressed_size,
+                locations,
+            });
+        }
+    }
+
+    Ok(ContentDedupReport {
+        groups,
+        reclaimable_bytes,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn zip(name: &str) -> PathToZip {
+        PathBuf::from(name).into()
+    }
+
+    fn path(name: &str) -> PathInsideZip {
+        PathBuf::from(name).into()
+    }
+
+    fn location(zip_name: &str, inside: &str, uncompressed_size: u64) -> EntryLocation {
+        EntryLocation {
+            zip_path: zip(zip_name),
+            inside_zip: path(inside),
+            compressed_size: uncompressed_size,
+            uncompressed_size,
+            crc32: 0,
+        }
+    }
+
+    fn hashes_for(
+        locations: &[EntryLocation],
+        full_hash: impl Fn(&EntryLocation) -> u128,
+    ) -> HashMap<(PathToZip, PathInsideZip), EntryHashes> {
+        locations
+            .iter()
+            .map(|loc| {
+                (
+                    (loc.zip_path.clone(), loc.inside_zip.clone()),
+                    EntryHashes {
+                        partial_hash: 0,
+                        full_hash: full_hash(loc),
+                        chunks: Vec::new(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn two_copies_at_different_paths_form_one_group() {
+        // The whole point of this module: path-agnostic, so a renamed/moved
+        // copy still counts as a duplicate.
+        let a = location("a.zip", "album1/photo.jpg", 100);
+        let b = location("b.zip", "album2/photo-renamed.jpg", 100);
+        let hashes = hashes_for(&[a.clone(), b.clone()], |_| 1);
+
+        let report = scan_by_content(&[a, b], &hashes).unwrap();
+
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].locations.len(), 2);
+        assert_eq!(report.reclaimable_bytes, 100);
+    }
+
+    #[test]
+    fn same_size_different_hash_is_not_a_duplicate() {
+        // Tier 1 (size) collides, but the content hash doesn't: must not be
+        // reported as reclaimable.
+        let a = location("a.zip", "photo.jpg", 100);
+        let b = location("b.zip", "other.jpg", 100);
+        let full_hash = |loc: &EntryLocation| if loc.zip_path == zip("a.zip") { 1 } else { 2 };
+        let hashes = hashes_for(&[a.clone(), b.clone()], full_hash);
+
+        let report = scan_by_content(&[a, b], &hashes).unwrap();
+
+        assert!(report.groups.is_empty());
+        assert_eq!(report.reclaimable_bytes, 0);
+    }
+
+    #[test]
+    fn a_lone_entry_is_never_a_duplicate_group() {
+        let a = location("a.zip", "photo.jpg", 100);
+        let hashes = hashes_for(std::slice::from_ref(&a), |_| 1);
+
+        let report = scan_by_content(&[a], &hashes).unwrap();
+
+        assert!(report.groups.is_empty());
+        assert_eq!(report.reclaimable_bytes, 0);
+    }
+
+    #[test]
+    fn three_copies_reclaim_two_copies_worth_of_bytes() {
+        let a = location("a.zip", "photo.jpg", 50);
+        let b = location("b.zip", "dup/photo.jpg", 50);
+        let c = location("c.zip", "dup2/photo.jpg", 50);
+        let hashes = hashes_for(&[a.clone(), b.clone(), c.clone()], |_| 1);
+
+        let report = scan_by_content(&[a, b, c], &hashes).unwrap();
+
+        assert_eq!(report.groups.len(), 1);
+        assert_eq!(report.groups[0].locations.len(), 3);
+        assert_eq!(report.reclaimable_bytes, 100);
+    }
+}
@@ -0,0 +1,95 @@
+//! Precomputes every hash downstream analyses need from a single read pass.
+//!
+//! [`crate::verify`], [`crate::chunk_dedup`], [`crate::content_dedup`], and
+//! [`crate::store`] all need to know an entry's partial hash, full content
+//! hash, and/or chunk hashes — but computing each independently meant
+//! reopening the source zip's central directory and re-streaming the same
+//! decompressed bytes once per analysis, per entry. This module reads each
+//! entry exactly once per zip (the archive itself is also opened only once
+//! per zip, not once per entry) and hands every analysis the same results.
+
+use std::collections::HashMap;
+use std::hash::Hasher;
+use std::sync::Arc;
+
+use positioned_io::RandomAccessFile;
+use rc_zip_tokio::ReadZip;
+use siphasher::sip128::Hasher128;
+use siphasher::sip128::SipHasher13;
+
+use crate::chunking::chunk_stream;
+use crate::path_inside_zip::PathInsideZip;
+use crate::path_to_zip::PathToZip;
+use crate::verify::EntryLocation;
+
+/// The number of leading bytes used for the partial hash in path-collision
+/// verification, matching ddh's cheap-then-expensive scheme.
+pub const PARTIAL_HASH_WINDOW: usize = 4096;
+
+/// Everything downstream analyses need about one entry's content, computed
+/// from a single decompressed read.
+#[derive(Debug, Clone)]
+pub struct EntryHashes {
+    pub partial_hash: u128,
+    pub full_hash: u128,
+    /// FastCDC chunk hashes and lengths, in stream order.
+    pub chunks: Vec<(u128, u64)>,
+}
+
+/// Compute [`EntryHashes`] for every location in `entries`, opening each
+/// source zip's archive exactly once no matter how many of its entries are
+/// being analyzed, and reading each entry's decompressed stream exactly once.
+pub async fn compute_entry_hashes(
+    entries: &[EntryLocation],
+) -> eyre::Result<HashMap<(PathToZip, PathInsideZip), EntryHashes>> {
+    let mut by_zip: HashMap<PathToZip, Vec<&EntryLocation>> = HashMap::new();
+    for loc in entries {
+        by_zip.entry(loc.zip_path.clone()).or_default().push(loc);
+    }
+
+    let mut out = HashMap::with_capacity(entries.len());
+    for (zip_path, locations) in by_zip {
+        let f = Arc::new(RandomAccessFile::open(&zip_path)?);
+        let archive = f.read_zip().await?;
+
+        for loc in locations {
+            let entry = archive
+                .by_name(loc.inside_zip.to_string_lossy())
+                .ok_or_else(|| {
+                    eyre::eyre!("Entry {:?} missing from {:?}", loc.inside_zip, zip_path)
+                })?;
+
+            let chunks = chunk_stream(entry.reader()).await?;
+
+            let mut full_hasher = SipHasher13::new();
+            let mut partial_hasher = SipHasher13::new();
+            let mut partial_remaining = PARTIAL_HASH_WINDOW;
+            let mut chunk_hashes = Vec::with_capacity(chunks.len());
+
+            for chunk in &chunks {
+                full_hasher.write(&chunk.data);
+
+                if partial_remaining > 0 {
+                    let take = partial_remaining.min(chunk.data.len());
+                    partial_hasher.write(&chunk.data[..take]);
+                    partial_remaining -= take;
+                }
+
+                let mut chunk_hasher = SipHasher13::new();
+                chunk_hasher.write(&chunk.data);
+                chunk_hashes.push((chunk_hasher.finish128().as_u128(), chunk.data.len() as u64));
+            }
+
+            out.insert(
+                (zip_path.clone(), loc.inside_zip.clone()),
+                EntryHashes {
+                    partial_hash: partial_hasher.finish128().as_u128(),
+                    full_hash: full_hasher.finish128().as_u128(),
+                    chunks: chunk_hashes,
+                },
+            );
+        }
+    }
+
+    Ok(out)
+}
@@ -0,0 +1,28 @@
+use std::path::PathBuf;
+
+use crate::path_to_zip::PathToZip;
+
+/// Walk `sources` looking for zip archives.
+///
+/// Returns the zip files found alongside any non-zip files encountered along
+/// the way, so callers can warn about stray files without treating them as a
+/// hard error.
+pub async fn get_zips(sources: &[PathBuf]) -> eyre::Result<(Vec<PathToZip>, Vec<PathBuf>)> {
+    let mut zips = Vec::new();
+    let mut skipped = Vec::new();
+
+    for source in sources {
+        let mut entries = tokio::fs::read_dir(source).await?;
+        while let Some(entry) = entries.next_entry().await? {
+            let path = entry.path();
+            if path.extension().and_then(|ext| ext.to_str()) == Some("zip") {
+                zips.push(PathToZip::from(path));
+            } else if path.is_file() {
+                skipped.push(path);
+            }
+        }
+    }
+
+    zips.sort();
+    Ok((zips, skipped))
+}
@@ -0,0 +1,8 @@
+use tracing::Level;
+use tracing_subscriber::FmtSubscriber;
+
+/// Install a process-wide `tracing` subscriber that logs at `level` and above.
+pub fn init_tracing(level: Level) {
+    let subscriber = FmtSubscriber::builder().with_max_level(level).finish();
+    let _ = tracing::subscriber::set_global_default(subscriber);
+}
@@ -0,0 +1,14 @@
+pub mod chunk_dedup;
+pub mod chunking;
+pub mod content_dedup;
+pub mod entry_hashes;
+pub mod get_zips;
+pub mod init_tracing;
+pub mod path_inside_zip;
+pub mod path_to_zip;
+pub mod repack;
+pub mod report;
+pub mod safe_output_name;
+pub mod state;
+pub mod store;
+pub mod verify;
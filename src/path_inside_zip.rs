@@ -0,0 +1,41 @@
+use std::fmt;
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A sanitized path of an entry relative to the root of its containing zip archive.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathInsideZip(Arc<PathBuf>);
+
+impl From<Arc<PathBuf>> for PathInsideZip {
+    fn from(path: Arc<PathBuf>) -> Self {
+        Self(path)
+    }
+}
+
+impl From<PathBuf> for PathInsideZip {
+    fn from(path: PathBuf) -> Self {
+        Self(Arc::new(path))
+    }
+}
+
+impl Deref for PathInsideZip {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for PathInsideZip {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl fmt::Display for PathInsideZip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
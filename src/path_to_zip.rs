@@ -0,0 +1,44 @@
+use std::fmt;
+use std::ops::Deref;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+/// A filesystem path to a zip archive.
+///
+/// Wrapping in `Arc` lets the same path be cloned cheaply into the many
+/// `HashMap`/`HashSet` keys the dedup analysis builds up.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct PathToZip(Arc<PathBuf>);
+
+impl From<Arc<PathBuf>> for PathToZip {
+    fn from(path: Arc<PathBuf>) -> Self {
+        Self(path)
+    }
+}
+
+impl From<PathBuf> for PathToZip {
+    fn from(path: PathBuf) -> Self {
+        Self(Arc::new(path))
+    }
+}
+
+impl Deref for PathToZip {
+    type Target = Path;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl AsRef<Path> for PathToZip {
+    fn as_ref(&self) -> &Path {
+        &self.0
+    }
+}
+
+impl fmt::Display for PathToZip {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0.display())
+    }
+}
@@ -0,0 +1,372 @@
+//! Vacuum/repack mode: turn the dedup analysis into rewritten archives.
+//!
+//! Modeled on zvault's bundle reclamation — for each duplicated
+//! `PathInsideZip`, a single "keeper" zip is chosen (the one that already
+//! holds the most unique bytes, to minimize how many archives need
+//! rewriting), and every other archive has that entry dropped. Archives
+//! whose usage ratio is already healthy are left untouched.
+//!
+//! Crucially, "duplicated" here means a [`VerifiedDedupClass`] with more than
+//! one member, not merely a shared `PathInsideZip`: two exports can hold a
+//! different revision of the same logical path, and repack must never drop
+//! one revision believing it's an interchangeable copy of the other.
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use chrono::Datelike;
+use chrono::Timelike;
+use positioned_io::RandomAccessFile;
+use rc_zip_tokio::ReadZip;
+use tokio::io::AsyncReadExt;
+use zip::ZipWriter;
+use zip::write::FileOptions;
+
+use crate::chunking::MAX_SIZE;
+use crate::path_inside_zip::PathInsideZip;
+use crate::path_to_zip::PathToZip;
+use crate::safe_output_name::safe_output_name;
+use crate::verify::VerifiedDedupClass;
+
+/// Where to write repacked archives, and how aggressively to rewrite.
+#[derive(Debug, Clone)]
+pub struct RepackConfig {
+    pub output_dir: PathBuf,
+    /// Archives whose usage ratio is already at or above this are left
+    /// untouched, since they're mostly unique and not worth rewriting.
+    pub rewrite_below_ratio: f64,
+}
+
+/// Per-archive outcome of a repack run, modeled on zvault's
+/// `BundleAnalysis::get_usage_ratio`.
+#[derive(Debug, Clone)]
+pub struct ArchiveUsage {
+    pub zip_path: PathToZip,
+    pub original_bytes: u64,
+    pub retained_bytes: u64,
+    pub rewritten: bool,
+}
+
+impl ArchiveUsage {
+    /// Fraction of the archive's original bytes retained in the output.
+    pub fn usage_ratio(&self) -> f64 {
+        if self.original_bytes == 0 {
+            1.0
+        } else {
+            self.retained_bytes as f64 / self.original_bytes as f64
+        }
+    }
+}
+
+/// Bytes each zip holds that are verified-unique: never collapsed into a
+/// multi-member dedup class, so that zip is the only place they exist.
+fn unique_bytes_by_zip(
+    verified: &HashMap<PathInsideZip, Vec<VerifiedDedupClass>>,
+) -> HashMap<PathToZip, u64> {
+    let mut unique = HashMap::new();
+    for classes in verified.values() {
+        for class in classes {
+            if let [loc] = class.members.as_slice() {
+                *unique.entry(loc.zip_path.clone()).or_default() += loc.uncompressed_size;
+            }
+        }
+    }
+    unique
+}
+
+/// For each verified dedup class with more than one member, pick the zip
+/// that should keep that content: whichever already holds the most unique
+/// (non-duplicated) bytes, to minimize how many archives need rewriting.
+///
+/// Keyed by `(PathInsideZip, full_hash)` rather than just `PathInsideZip`,
+/// since a single path can legitimately hold more than one verified class
+/// (different revisions at the same path across exports).
+fn choose_keepers(
+    verified: &HashMap<PathInsideZip, Vec<VerifiedDedupClass>>,
+    unique_bytes_by_zip: &HashMap<PathToZip, u64>,
+) -> HashMap<(PathInsideZip, u128), PathToZip> {
+    let mut keepers = HashMap::new();
+    for (path, classes) in verified {
+        for class in classes {
+            if class.members.len() < 2 {
+                continue;
+            }
+            let keeper = class
+                .members
+                .iter()
+                .max_by_key(|loc| unique_bytes_by_zip.get(&loc.zip_path).copied().unwrap_or(0))
+                .expect("members is non-empty")
+                .zip_path
+                .clone();
+            keepers.insert((path.clone(), class.full_hash), keeper);
+        }
+    }
+    keepers
+}
+
+/// Which verified class (by full content hash) each `(zip, path)` location
+/// belongs to, so a per-zip entry scan can classify an entry without
+/// re-running the verification pass.
+fn location_classes(
+    verified: &HashMap<PathInsideZip, Vec<VerifiedDedupClass>>,
+) -> HashMap<(PathToZip, PathInsideZip), u128> {
+    let mut classes = HashMap::new();
+    for (path, groups) in verified {
+        for group in groups {
+            for loc in &group.members {
+                classes.insert((loc.zip_path.clone(), path.clone()), group.full_hash);
+            }
+        }
+    }
+    classes
+}
+
+/// Repack `zips` according to `config`, dropping only entries that are in the
+/// same *verified* dedup class as another archive's keeper. Returns the
+/// per-archive usage ratio for every input zip, whether or not it was
+/// actually rewritten.
+pub async fn repack(
+    zips: &[PathToZip],
+    verified: &HashMap<PathInsideZip, Vec<VerifiedDedupClass>>,
+    config: &RepackConfig,
+) -> eyre::Result<Vec<ArchiveUsage>> {
+    let unique_bytes = unique_bytes_by_zip(verified);
+    let keepers = choose_keepers(verified, &unique_bytes);
+    let location_classes = location_classes(verified);
+
+    std::fs::create_dir_all(&config.output_dir)?;
+    let mut usages = Vec::with_capacity(zips.len());
+
+    for zip_path in zips {
+        let f = Arc::new(RandomAccessFile::open(zip_path)?);
+        let archive = f.read_zip().await?;
+
+        let mut original_bytes = 0u64;
+        let mut retained_bytes = 0u64;
+        let mut keep_entries: Vec<(String, PathInsideZip)> = Vec::new();
+
+        for entry in archive.entries() {
+            let name = entry
+                .sanitized_name()
+                .ok_or_else(|| eyre::eyre!("Entry had evil name: {:?}", entry.name))?;
+            let inside_zip: PathInsideZip = PathBuf::from(name).into();
+            original_bytes += entry.uncompressed_size;
+
+            // Only treat an entry as droppable when it's a member of a
+            // verified, multi-member dedup class whose keeper is some other
+            // zip. A path with no verified class, or a class of one, is a
+            // distinct revision that must never be dropped.
+            let is_keeper = match location_classes.get(&(zip_path.clone(), inside_zip.clone())) {
+                Some(full_hash) => keepers
+                    .get(&(inside_zip.clone(), *full_hash))
+                    .is_none_or(|keeper| keeper == zip_path),
+                None => true,
+            };
+            if is_keeper {
+                retained_bytes += entry.uncompressed_size;
+                keep_entries.push((name.to_string(), inside_zip));
+            }
+        }
+
+        let usage = ArchiveUsage {
+            zip_path: zip_path.clone(),
+            original_bytes,
+            retained_bytes,
+            rewritten: false,
+        };
+
+        if usage.usage_ratio() >= config.rewrite_below_ratio {
+            usages.push(usage);
+            continue;
+        }
+
+        // Two source zips taken at different times commonly share a
+        // basename, so the output name must be derived from the full source
+        // path, not just `file_name()`, to avoid one archive's repacked
+        // output silently overwriting another's.
+        let out_path = config.output_dir.join(safe_output_name(zip_path)?);
+        write_pruned_archive(zip_path, &out_path, &keep_entries).await?;
+
+        usages.push(ArchiveUsage {
+            rewritten: true,
+            ..usage
+        });
+    }
+
+    Ok(usages)
+}
+
+/// Stream the entries in `keep_entries` from `source` into a fresh, valid zip
+/// archive at `out_path`, so downstream takeout tooling still reads it.
+async fn write_pruned_archive(
+    source: &PathToZip,
+    out_path: &Path,
+    keep_entries: &[(String, PathInsideZip)],
+) -> eyre::Result<()> {
+    let f = Arc::new(RandomAccessFile::open(source)?);
+    let archive = f.read_zip().await?;
+
+    let out_file = File::create(out_path)?;
+    let mut writer = ZipWriter::new(out_file);
+
+    for (name, inside_zip) in keep_entries {
+        let entry = archive
+            .by_name(inside_zip.to_string_lossy())
+            .ok_or_else(|| eyre::eyre!("Entry {:?} missing from {:?}", inside_zip, source))?;
+
+        // Carry the source entry's mtime into the rewritten archive so
+        // repacking doesn't degrade every kept entry's metadata, not just
+        // drop the duplicates it's meant to.
+        let mut options: FileOptions<()> = FileOptions::default();
+        if let Some(modified) = to_zip_datetime(entry.modified) {
+            options = options.last_modified_time(modified);
+        }
+
+        writer.start_file(name, options)?;
+
+        // Copy in fixed-size windows rather than buffering the whole entry:
+        // a takeout export can hold multi-gigabyte files, and `entry.reader()`
+        // is async while `ZipWriter` is a sync `Write`, so there's no single
+        // `io::copy` that bridges the two directly.
+        let mut reader = entry.reader();
+        let mut buf = vec![0u8; MAX_SIZE];
+        loop {
+            let n = reader.read(&mut buf).await?;
+            if n == 0 {
+                break;
+            }
+            writer.write_all(&buf[..n])?;
+        }
+    }
+
+    writer.finish()?;
+    Ok(())
+}
+
+/// Convert an entry's modification time into the `zip` crate's coarser,
+/// MS-DOS-era timestamp representation. Returns `None` (dropping the mtime
+/// rather than failing the whole repack) if it falls outside that format's
+/// representable range.
+fn to_zip_datetime(modified: chrono::DateTime<chrono::Utc>) -> Option<zip::DateTime> {
+    zip::DateTime::from_date_and_time(
+        modified.year().try_into().ok()?,
+        modified.month() as u8,
+        modified.day() as u8,
+        modified.hour() as u8,
+        modified.minute() as u8,
+        modified.second() as u8,
+    )
+    .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::verify::EntryLocation;
+
+    fn zip(name: &str) -> PathToZip {
+        PathBuf::from(name).into()
+    }
+
+    fn path(name: &str) -> PathInsideZip {
+        PathBuf::from(name).into()
+    }
+
+    fn location(zip_name: &str, inside: &str, uncompressed_size: u64) -> EntryLocation {
+        EntryLocation {
+            zip_path: zip(zip_name),
+            inside_zip: path(inside),
+            compressed_size: uncompressed_size,
+            uncompressed_size,
+            crc32: 0,
+        }
+    }
+
+    fn class(full_hash: u128, members: Vec<EntryLocation>) -> VerifiedDedupClass {
+        VerifiedDedupClass { full_hash, members }
+    }
+
+    #[test]
+    fn usage_ratio_is_retained_over_original() {
+        let usage = ArchiveUsage {
+            zip_path: zip("a.zip"),
+            original_bytes: 200,
+            retained_bytes: 50,
+            rewritten: false,
+        };
+        assert_eq!(usage.usage_ratio(), 0.25);
+    }
+
+    #[test]
+    fn usage_ratio_of_an_empty_archive_is_whole() {
+        let usage = ArchiveUsage {
+            zip_path: zip("a.zip"),
+            original_bytes: 0,
+            retained_bytes: 0,
+            rewritten: false,
+        };
+        assert_eq!(usage.usage_ratio(), 1.0);
+    }
+
+    #[test]
+    fn unique_bytes_only_counts_single_member_classes() {
+        let mut verified = HashMap::new();
+        verified.insert(
+            path("shared.jpg"),
+            vec![class(
+                1,
+                vec![location("a.zip", "shared.jpg", 10), location("b.zip", "shared.jpg", 10)],
+            )],
+        );
+        verified.insert(
+            path("unique.jpg"),
+            vec![class(2, vec![location("a.zip", "unique.jpg", 30)])],
+        );
+
+        let unique = unique_bytes_by_zip(&verified);
+
+        assert_eq!(unique.get(&zip("a.zip")), Some(&30));
+        assert_eq!(unique.get(&zip("b.zip")), None);
+    }
+
+    #[test]
+    fn keeper_is_the_zip_with_the_most_unique_bytes() {
+        let mut verified = HashMap::new();
+        verified.insert(
+            path("shared.jpg"),
+            vec![class(
+                1,
+                vec![
+                    location("a.zip", "shared.jpg", 10),
+                    location("b.zip", "shared.jpg", 10),
+                ],
+            )],
+        );
+        let mut unique_bytes = HashMap::new();
+        unique_bytes.insert(zip("a.zip"), 100);
+        unique_bytes.insert(zip("b.zip"), 5);
+
+        let keepers = choose_keepers(&verified, &unique_bytes);
+
+        assert_eq!(keepers.get(&(path("shared.jpg"), 1)), Some(&zip("a.zip")));
+    }
+
+    #[test]
+    fn single_member_classes_never_become_keeper_entries() {
+        let mut verified = HashMap::new();
+        verified.insert(
+            path("unique.jpg"),
+            vec![class(2, vec![location("a.zip", "unique.jpg", 30)])],
+        );
+
+        let keepers = choose_keepers(&verified, &HashMap::new());
+
+        assert!(keepers.is_empty());
+    }
+}
@@ -0,0 +1,174 @@
+//! Machine-readable dedup reports.
+//!
+//! The `println!`-based report is only useful to a human at a terminal.
+//! [`DedupReport`] carries the same numbers — raw `u64` byte counts, not
+//! `format_bytes`-style formatted strings — so other programs can
+//! consume them as JSON, or as NDJSON when `sources` is large enough that
+//! streaming one record at a time matters.
+
+use serde::Serialize;
+
+use crate::path_to_zip::PathToZip;
+
+/// Per-zip totals: how many bytes it holds, and how many of those are also
+/// present in at least one other zip.
+#[derive(Debug, Clone, Serialize)]
+pub struct ZipStats {
+    pub zip_path: String,
+    pub total_bytes: u64,
+    pub duplicate_bytes: u64,
+}
+
+/// How much two zips overlap: shared paths, and the duplicated bytes they
+/// account for.
+#[derive(Debug, Clone, Serialize)]
+pub struct PairOverlap {
+    pub zip_a: String,
+    pub zip_b: String,
+    pub shared_paths: u64,
+    pub shared_bytes: u64,
+}
+
+/// The full structured dedup report: everything the human-readable prints
+/// cover, as raw numbers.
+#[derive(Debug, Clone, Serialize)]
+pub struct DedupReport {
+    pub zips: Vec<ZipStats>,
+    pub pairs: Vec<PairOverlap>,
+    pub total_savable_bytes: u64,
+    pub total_bytes: u64,
+    pub percent_reduction: f64,
+}
+
+impl DedupReport {
+    pub fn zip_stats(zip_path: &PathToZip, total_bytes: u64, duplicate_bytes: u64) -> ZipStats {
+        ZipStats {
+            zip_path: zip_path.to_string(),
+            total_bytes,
+            duplicate_bytes,
+        }
+    }
+
+    pub fn pair_overlap(
+        zip_a: &PathToZip,
+        zip_b: &PathToZip,
+        shared_paths: u64,
+        shared_bytes: u64,
+    ) -> PairOverlap {
+        PairOverlap {
+            zip_a: zip_a.to_string(),
+            zip_b: zip_b.to_string(),
+            shared_paths,
+            shared_bytes,
+        }
+    }
+
+    /// Serialize the whole report as a single pretty-printed JSON document.
+    pub fn to_json(&self) -> eyre::Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Serialize as NDJSON: one record per line, zips then pairs then a
+    /// trailing summary record, so a consumer can process it a line at a
+    /// time. The whole output is built as one `String` from the finished
+    /// report.
+    pub fn to_ndjson(&self) -> eyre::Result<String> {
+        #[derive(Serialize)]
+        #[serde(tag = "kind", rename_all = "snake_case")]
+        enum Record<'a> {
+            Zip(&'a ZipStats),
+            Pair(&'a PairOverlap),
+            Summary {
+                total_savable_bytes: u64,
+                total_bytes: u64,
+                percent_reduction: f64,
+            },
+        }
+
+        let mut out = String::new();
+        for zip in &self.zips {
+            out.push_str(&serde_json::to_string(&Record::Zip(zip))?);
+            out.push('\n');
+        }
+        for pair in &self.pairs {
+            out.push_str(&serde_json::to_string(&Record::Pair(pair))?);
+            out.push('\n');
+        }
+        out.push_str(&serde_json::to_string(&Record::Summary {
+            total_savable_bytes: self.total_savable_bytes,
+            total_bytes: self.total_bytes,
+            percent_reduction: self.percent_reduction,
+        })?);
+        out.push('\n');
+        Ok(out)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn zip(name: &str) -> PathToZip {
+        PathBuf::from(name).into()
+    }
+
+    fn sample_report() -> DedupReport {
+        DedupReport {
+            zips: vec![DedupReport::zip_stats(&zip("a.zip"), 100, 40)],
+            pairs: vec![DedupReport::pair_overlap(&zip("a.zip"), &zip("b.zip"), 2, 40)],
+            total_savable_bytes: 40,
+            total_bytes: 100,
+            percent_reduction: 40.0,
+        }
+    }
+
+    #[test]
+    fn zip_stats_carries_the_path_and_byte_counts_through() {
+        let stats = DedupReport::zip_stats(&zip("a.zip"), 100, 40);
+        assert_eq!(stats.zip_path, zip("a.zip").to_string());
+        assert_eq!(stats.total_bytes, 100);
+        assert_eq!(stats.duplicate_bytes, 40);
+    }
+
+    #[test]
+    fn to_json_round_trips_through_serde_value() {
+        let report = sample_report();
+        let json = report.to_json().unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+
+        assert_eq!(value["zips"][0]["zip_path"], zip("a.zip").to_string());
+        assert_eq!(value["total_savable_bytes"], 40);
+        assert_eq!(value["percent_reduction"], 40.0);
+    }
+
+    #[test]
+    fn to_ndjson_emits_one_line_per_zip_then_pair_then_a_trailing_summary() {
+        let report = sample_report();
+        let ndjson = report.to_ndjson().unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+
+        assert_eq!(lines.len(), 3);
+        assert!(lines[0].contains("\"kind\":\"zip\""));
+        assert!(lines[1].contains("\"kind\":\"pair\""));
+        assert!(lines[2].contains("\"kind\":\"summary\""));
+        assert!(ndjson.ends_with('\n'));
+    }
+
+    #[test]
+    fn to_ndjson_with_no_zips_or_pairs_is_just_the_summary() {
+        let report = DedupReport {
+            zips: Vec::new(),
+            pairs: Vec::new(),
+            total_savable_bytes: 0,
+            total_bytes: 0,
+            percent_reduction: 0.0,
+        };
+        let ndjson = report.to_ndjson().unwrap();
+        let lines: Vec<&str> = ndjson.lines().collect();
+
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].contains("\"kind\":\"summary\""));
+    }
+}
@@ -0,0 +1,30 @@
+//! A collision-safe output file name derived from a zip's full source path.
+//!
+//! Separate takeout exports commonly reuse the same zip basename (that's the
+//! whole premise [`crate::verify`] is built on: the same logical path can
+//! hold a different revision across separate exports), so deriving an output
+//! file name from the basename alone lets two unrelated source zips silently
+//! overwrite each other's manifest or repacked archive.
+
+use std::hash::Hasher;
+
+use siphasher::sip128::Hasher128;
+use siphasher::sip128::SipHasher13;
+
+use crate::path_to_zip::PathToZip;
+
+/// A file name safe to write under a shared output directory: the original
+/// basename, prefixed with a hash of the zip's full source path so that two
+/// source zips sharing a basename never collide.
+pub fn safe_output_name(zip_path: &PathToZip) -> eyre::Result<String> {
+    let file_name = zip_path
+        .file_name()
+        .ok_or_else(|| eyre::eyre!("Zip path {:?} has no file name", zip_path.as_ref()))?
+        .to_string_lossy();
+
+    let mut hasher = SipHasher13::new();
+    hasher.write(zip_path.to_string_lossy().as_bytes());
+    let hash = hasher.finish128().as_u128() as u64;
+
+    Ok(format!("{hash:016x}-{file_name}"))
+}
@@ -0,0 +1,43 @@
+use std::path::PathBuf;
+
+/// How the dedup report should be rendered.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportFormat {
+    /// The original `println!`-based, human-readable report.
+    #[default]
+    Human,
+    /// A single pretty-printed JSON document.
+    Json,
+    /// One JSON record per line, for streaming large `sources`.
+    Ndjson,
+}
+
+/// A named set of takeout directories to analyze, plus the options that
+/// control how the analysis is run and reported.
+#[derive(Debug, Clone)]
+pub struct Profile {
+    pub name: String,
+    pub sources: Vec<PathBuf>,
+    /// When set, analysis also materializes a content-addressed store rooted
+    /// at this directory instead of only reporting dedup statistics.
+    pub store_root: Option<PathBuf>,
+    /// When set, analysis also repacks archives with low usage ratios into
+    /// pruned copies instead of only reporting dedup statistics.
+    pub repack: Option<crate::repack::RepackConfig>,
+    /// How the dedup report should be rendered.
+    pub report_format: ReportFormat,
+}
+
+impl Profile {
+    /// A profile pointed at the bundled example takeout directory, used by
+    /// the `examples/` binaries until real profile persistence lands.
+    pub fn new_example() -> Self {
+        Self {
+            name: "example".to_string(),
+            sources: vec![PathBuf::from("./example_data")],
+            store_root: None,
+            repack: None,
+            report_format: ReportFormat::Human,
+        }
+    }
+}
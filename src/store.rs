@@ -0,0 +1,223 @@
+//! A content-addressed store that materializes dedup analysis into an actual
+//! on-disk result, instead of only reporting statistics.
+//!
+//! Every unique entry (by full content hash) is written once into a blob
+//! directory; each source zip gets a manifest mapping its original
+//! `PathInsideZip` entries back to the blob that holds their bytes.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use positioned_io::RandomAccessFile;
+use rc_zip_tokio::ReadZip;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::entry_hashes::EntryHashes;
+use crate::path_inside_zip::PathInsideZip;
+use crate::path_to_zip::PathToZip;
+use crate::safe_output_name::safe_output_name;
+
+/// Where a single zip's entry ended up in the store, plus enough metadata to
+/// reconstruct the original file.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ManifestEntry {
+    pub blob_hash: String,
+    pub uncompressed_size: u64,
+    pub mtime_unix: Option<i64>,
+}
+
+/// A zip's full `PathInsideZip -> ManifestEntry` mapping, keyed by the
+/// entry's path rendered as a string so it round-trips through JSON.
+pub type Manifest = HashMap<String, ManifestEntry>;
+
+/// Totals from a completed [`Store::build`] run.
+#[derive(Debug, Default, Clone)]
+pub struct StoreStats {
+    pub entries_written: u64,
+    pub blobs_written: u64,
+    pub bytes_written: u64,
+    pub bytes_deduplicated: u64,
+}
+
+/// A content-addressed store rooted at a directory on disk.
+pub struct Store {
+    root: PathBuf,
+}
+
+impl Store {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        Self { root: root.into() }
+    }
+
+    fn blobs_dir(&self) -> PathBuf {
+        self.root.join("blobs")
+    }
+
+    fn manifests_dir(&self) -> PathBuf {
+        self.root.join("manifests")
+    }
+
+    /// Fan blobs out by the first two hex characters of their hash so no
+    /// single directory ends up holding every blob in the store.
+    fn blob_path(&self, hash_hex: &str) -> PathBuf {
+        self.blobs_dir().join(&hash_hex[..2]).join(hash_hex)
+    }
+
+    /// Stream every entry from `zips` into the store: one blob per unique
+    /// content hash, one manifest per source zip.
+    ///
+    /// `hashes` must already hold an [`EntryHashes`] entry for every entry in
+    /// every zip in `zips` (see
+    /// [`crate::entry_hashes::compute_entry_hashes`]) — each archive is
+    /// opened exactly once here, to stream blob bytes, rather than once per
+    /// analysis.
+    pub async fn build(
+        &self,
+        zips: &[PathToZip],
+        hashes: &HashMap<(PathToZip, PathInsideZip), EntryHashes>,
+    ) -> eyre::Result<StoreStats> {
+        fs::create_dir_all(self.blobs_dir())?;
+        fs::create_dir_all(self.manifests_dir())?;
+
+        let mut stats = StoreStats::default();
+        let mut seen_hashes: HashSet<String> = HashSet::new();
+
+        for zip_path in zips {
+            let f = Arc::new(RandomAccessFile::open(zip_path)?);
+            let archive = f.read_zip().await?;
+            let mut manifest: Manifest = HashMap::new();
+
+            for entry in archive.entries() {
+                let name = entry.sanitized_name().ok_or_else(|| {
+                    eyre::eyre!("Entry had evil name: {:?}", entry.name)
+                })?;
+                let inside_zip: PathInsideZip = PathBuf::from(name).into();
+                let entry_hashes = hashes
+                    .get(&(zip_path.clone(), inside_zip.clone()))
+                    .ok_or_else(|| {
+                        eyre::eyre!("No precomputed hashes for {:?} in {:?}", inside_zip, zip_path)
+                    })?;
+                let hash_hex = format!("{:032x}", entry_hashes.full_hash);
+                let uncompressed_size = entry.uncompressed_size;
+                let mtime_unix = Some(entry.modified.timestamp());
+
+                stats.entries_written += 1;
+                if seen_hashes.insert(hash_hex.clone()) {
+                    let blob_path = self.blob_path(&hash_hex);
+                    if !blob_path.exists() {
+                        fs::create_dir_all(
+                            blob_path.parent().expect("blob path always has a parent"),
+                        )?;
+
+                        // Write via a temp path and rename so a crash
+                        // mid-write can't leave a corrupt blob under its
+                        // final, trusted hash-addressed name.
+                        let tmp_path = blob_path.with_extension("tmp");
+                        let mut out = tokio::fs::File::create(&tmp_path).await?;
+                        tokio::io::copy(&mut entry.reader(), &mut out).await?;
+                        fs::rename(&tmp_path, &blob_path)?;
+
+                        stats.blobs_written += 1;
+                        stats.bytes_written += uncompressed_size;
+                    } else {
+                        stats.bytes_deduplicated += uncompressed_size;
+                    }
+                } else {
+                    stats.bytes_deduplicated += uncompressed_size;
+                }
+
+                manifest.insert(
+                    name.to_string(),
+                    ManifestEntry {
+                        blob_hash: hash_hex,
+                        uncompressed_size,
+                        mtime_unix,
+                    },
+                );
+            }
+
+            self.write_manifest(zip_path, &manifest)?;
+        }
+
+        Ok(stats)
+    }
+
+    fn write_manifest(&self, zip_path: &PathToZip, manifest: &Manifest) -> eyre::Result<()> {
+        // Two source zips taken at different times commonly share a
+        // basename, so the manifest name must be derived from the full
+        // source path, not just `file_name()`, to avoid one export's
+        // manifest silently overwriting another's.
+        let manifest_path = self
+            .manifests_dir()
+            .join(safe_output_name(zip_path)?)
+            .with_extension("json");
+        let json = serde_json::to_vec_pretty(manifest)?;
+        fs::write(manifest_path, json)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    /// A fresh scratch directory under the OS temp dir, unique per test via
+    /// `name`, cleaned up on drop so repeated runs don't see stale state.
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new(name: &str) -> Self {
+            let path = std::env::temp_dir().join(format!("thrumzip-store-test-{name}-{}", std::process::id()));
+            let _ = fs::remove_dir_all(&path);
+            fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn blob_path_fans_out_by_the_first_two_hex_characters() {
+        let dir = TempDir::new("blob-path");
+        let store = Store::new(dir.0.clone());
+
+        let path = store.blob_path("abcd1234");
+
+        assert_eq!(
+            path,
+            dir.0.join("blobs").join("ab").join("abcd1234")
+        );
+    }
+
+    #[test]
+    fn write_manifest_names_the_file_from_the_full_source_path_not_the_basename() {
+        // Two zips with the same basename under different source directories
+        // must not collide on the same manifest file.
+        let dir = TempDir::new("write-manifest");
+        let store = Store::new(dir.0.clone());
+        fs::create_dir_all(store.manifests_dir()).unwrap();
+
+        let zip_a: PathToZip = PathBuf::from("/exports/2024/Takeout.zip").into();
+        let zip_b: PathToZip = PathBuf::from("/exports/2025/Takeout.zip").into();
+        let manifest: Manifest = HashMap::new();
+
+        store.write_manifest(&zip_a, &manifest).unwrap();
+        store.write_manifest(&zip_b, &manifest).unwrap();
+
+        let entries: Vec<_> = fs::read_dir(store.manifests_dir())
+            .unwrap()
+            .map(|e| e.unwrap().file_name())
+            .collect();
+        assert_eq!(entries.len(), 2);
+    }
+}
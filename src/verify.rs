@@ -0,0 +1,257 @@
+//! Confirms that entries sharing a [`PathInsideZip`] actually share content.
+//!
+//! `entry_map` groups entries purely by their path inside the zip, which is
+//! only a *candidate* for deduplication: across separate takeout exports the
+//! same logical path can hold a different revision of the file. This module
+//! runs the ddh-style cheap-then-expensive verification pass so callers can
+//! trust that a "savable" byte was actually a duplicate.
+
+use std::collections::HashMap;
+
+use crate::entry_hashes::EntryHashes;
+use crate::path_inside_zip::PathInsideZip;
+use crate::path_to_zip::PathToZip;
+
+/// A single occurrence of an entry inside one of the zips being analyzed.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct EntryLocation {
+    pub zip_path: PathToZip,
+    pub inside_zip: PathInsideZip,
+    pub compressed_size: u64,
+    pub uncompressed_size: u64,
+    pub crc32: u32,
+}
+
+/// A group of [`EntryLocation`]s confirmed to hold byte-identical content.
+#[derive(Debug, Clone)]
+pub struct VerifiedDedupClass {
+    pub full_hash: u128,
+    pub members: Vec<EntryLocation>,
+}
+
+/// Split each `entry_map` group into verified dedup classes.
+///
+/// Entries that only coincidentally share a `(compressed_size, crc32)` pair,
+/// or whose full content diverges past the partial hash, end up in separate
+/// classes so `total_savable` only ever counts true duplicates.
+///
+/// `hashes` must already hold an [`EntryHashes`] entry for every location in
+/// `entry_map` (see [`crate::entry_hashes::compute_entry_hashes`]) — this pass
+/// only classifies, it never touches the zip archives itself.
+pub fn verify_path_collisions(
+    entry_map: &HashMap<PathInsideZip, Vec<EntryLocation>>,
+    hashes: &HashMap<(PathToZip, PathInsideZip), EntryHashes>,
+) -> eyre::Result<HashMap<PathInsideZip, Vec<VerifiedDedupClass>>> {
+    let mut verified = HashMap::with_capacity(entry_map.len());
+
+    for (path, locations) in entry_map {
+        if locations.len() < 2 {
+            // Nothing to collide with; trivially its own class.
+            if let Some(loc) = locations.first() {
+                verified.insert(
+                    path.clone(),
+                    vec![VerifiedDedupClass {
+                        full_hash: 0,
+                        members: vec![loc.clone()],
+                    }],
+                );
+            }
+            continue;
+        }
+
+        let classes = verify_one_group(locations, hashes)?;
+        verified.insert(path.clone(), classes);
+    }
+
+    Ok(verified)
+}
+
+/// Verify a single `(PathInsideZip, [locations])` group, splitting it into
+/// however many distinct content classes it actually contains.
+fn verify_one_group(
+    locations: &[EntryLocation],
+    hashes: &HashMap<(PathToZip, PathInsideZip), EntryHashes>,
+) -> eyre::Result<Vec<VerifiedDedupClass>> {
+    // Tier 1: group by the cheap central-directory fields.
+    let mut by_cheap_key: HashMap<(u64, u32), Vec<&EntryLocation>> = HashMap::new();
+    for loc in locations {
+        by_cheap_key
+            .entry((loc.compressed_size, loc.crc32))
+            .or_default()
+            .push(loc);
+    }
+
+    let mut classes = Vec::new();
+    for candidates in by_cheap_key.into_values() {
+        if candidates.len() == 1 {
+            classes.push(VerifiedDedupClass {
+                full_hash: 0,
+                members: vec![candidates[0].clone()],
+            });
+            continue;
+        }
+
+        // Tier 2: partial SipHash-128 over the first few KiB of each entry.
+        let mut by_partial_hash: HashMap<u128, Vec<&EntryLocation>> = HashMap::new();
+        for loc in &candidates {
+            let partial = entry_hashes_for(loc, hashes)?.partial_hash;
+            by_partial_hash.entry(partial).or_default().push(loc);
+        }
+
+        for still_colliding in by_partial_hash.into_values() {
+            if still_colliding.len() == 1 {
+                classes.push(VerifiedDedupClass {
+                    full_hash: 0,
+                    members: vec![still_colliding[0].clone()],
+                });
+                continue;
+            }
+
+            // Tier 3: full streaming hash of the decompressed entry.
+            let mut by_full_hash: HashMap<u128, Vec<EntryLocation>> = HashMap::new();
+            for loc in still_colliding {
+                let full = entry_hashes_for(loc, hashes)?.full_hash;
+                by_full_hash.entry(full).or_default().push(loc.clone());
+            }
+
+            for (full_hash, members) in by_full_hash {
+                classes.push(VerifiedDedupClass { full_hash, members });
+            }
+        }
+    }
+
+    Ok(classes)
+}
+
+fn entry_hashes_for<'a>(
+    loc: &EntryLocation,
+    hashes: &'a HashMap<(PathToZip, PathInsideZip), EntryHashes>,
+) -> eyre::Result<&'a EntryHashes> {
+    hashes
+        .get(&(loc.zip_path.clone(), loc.inside_zip.clone()))
+        .ok_or_else(|| {
+            eyre::eyre!(
+                "No precomputed hashes for {:?} in {:?}",
+                loc.inside_zip,
+                loc.zip_path
+            )
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+
+    fn zip(name: &str) -> PathToZip {
+        PathBuf::from(name).into()
+    }
+
+    fn path(name: &str) -> PathInsideZip {
+        PathBuf::from(name).into()
+    }
+
+    fn location(zip_name: &str, inside: &str, compressed_size: u64, crc32: u32) -> EntryLocation {
+        EntryLocation {
+            zip_path: zip(zip_name),
+            inside_zip: path(inside),
+            compressed_size,
+            uncompressed_size: compressed_size,
+            crc32,
+        }
+    }
+
+    fn hashes_for(
+        locations: &[EntryLocation],
+        partial_hash: impl Fn(&EntryLocation) -> u128,
+        full_hash: impl Fn(&EntryLocation) -> u128,
+    ) -> HashMap<(PathToZip, PathInsideZip), EntryHashes> {
+        locations
+            .iter()
+            .map(|loc| {
+                (
+                    (loc.zip_path.clone(), loc.inside_zip.clone()),
+                    EntryHashes {
+                        partial_hash: partial_hash(loc),
+                        full_hash: full_hash(loc),
+                        chunks: Vec::new(),
+                    },
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn tier_one_rejects_a_cheap_key_mismatch() {
+        // Same CRC-32, different compressed size: never even considered for
+        // the same class, regardless of what the hashes say.
+        let a = location("a.zip", "photo.jpg", 100, 42);
+        let b = location("b.zip", "photo.jpg", 200, 42);
+        let hashes = hashes_for(&[a.clone(), b.clone()], |_| 1, |_| 1);
+
+        let classes = verify_one_group(&[a, b], &hashes).unwrap();
+
+        assert_eq!(classes.len(), 2);
+        assert!(classes.iter().all(|c| c.members.len() == 1));
+    }
+
+    #[test]
+    fn tier_two_splits_a_cheap_key_collision_with_different_partial_hashes() {
+        // Same (compressed_size, crc32) by coincidence, but the partial hash
+        // over the first bytes already diverges.
+        let a = location("a.zip", "photo.jpg", 100, 42);
+        let b = location("b.zip", "photo.jpg", 100, 42);
+        let partial_hash = |loc: &EntryLocation| if loc.zip_path == zip("a.zip") { 1 } else { 2 };
+        let hashes = hashes_for(&[a.clone(), b.clone()], partial_hash, |_| 99);
+
+        let classes = verify_one_group(&[a, b], &hashes).unwrap();
+
+        assert_eq!(classes.len(), 2);
+        assert!(classes.iter().all(|c| c.members.len() == 1));
+    }
+
+    #[test]
+    fn tier_three_splits_a_partial_hash_collision_with_different_full_hashes() {
+        // Partial hashes collide too, but the full content hash reveals
+        // they're actually different files — the scenario chunk0-1 exists to
+        // prevent a different revision of a file from being treated as a
+        // duplicate just because its prefix and CRC happen to match.
+        let a = location("a.zip", "photo.jpg", 100, 42);
+        let b = location("b.zip", "photo.jpg", 100, 42);
+        let full_hash = |loc: &EntryLocation| if loc.zip_path == zip("a.zip") { 11 } else { 22 };
+        let hashes = hashes_for(&[a.clone(), b.clone()], |_| 1, full_hash);
+
+        let classes = verify_one_group(&[a, b], &hashes).unwrap();
+
+        assert_eq!(classes.len(), 2);
+        assert!(classes.iter().all(|c| c.members.len() == 1));
+    }
+
+    #[test]
+    fn entries_with_matching_hashes_at_every_tier_form_one_class() {
+        let a = location("a.zip", "photo.jpg", 100, 42);
+        let b = location("b.zip", "photo.jpg", 100, 42);
+        let hashes = hashes_for(&[a.clone(), b.clone()], |_| 1, |_| 99);
+
+        let classes = verify_one_group(&[a, b], &hashes).unwrap();
+
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].full_hash, 99);
+        assert_eq!(classes[0].members.len(), 2);
+    }
+
+    #[test]
+    fn a_single_location_needs_no_verification() {
+        let mut entry_map = HashMap::new();
+        let loc = location("a.zip", "photo.jpg", 100, 42);
+        entry_map.insert(path("photo.jpg"), vec![loc]);
+        let hashes = HashMap::new();
+
+        let verified = verify_path_collisions(&entry_map, &hashes).unwrap();
+
+        let classes = &verified[&path("photo.jpg")];
+        assert_eq!(classes.len(), 1);
+        assert_eq!(classes[0].members.len(), 1);
+    }
+}